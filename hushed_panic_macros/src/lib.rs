@@ -0,0 +1,93 @@
+//!
+//! Attribute macros for `hushed_panic`.
+//!
+//! Use these through the re-exports in `hushed_panic`.
+//!
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::Parser;
+use syn::{parse_macro_input, ItemFn, LitStr};
+
+/// Parses the optional `expected = "..."` argument shared by
+/// the attributes.
+fn parse_expected(args: TokenStream, attribute: &str) -> syn::Result<Option<LitStr>> {
+    let mut expected = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("expected") {
+            expected = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error(format!("unsupported `{}` argument, expected `expected = \"...\"`", attribute)))
+        }
+    });
+    parser.parse(args)?;
+
+    Ok(expected)
+}
+
+/// Marks a function as a test whose panics are hushed.
+///
+/// With `expected = "..."`, only panics whose message contains
+/// the string are hushed, and the test must panic with such a
+/// message, as with `#[should_panic(expected = "...")]`.
+#[proc_macro_attribute]
+pub fn hushed_test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let expected = match parse_expected(args, "hushed_test") {
+        Ok(expected) => expected,
+        Err(error) => return error.into_compile_error().into(),
+    };
+
+    let ItemFn { attrs, vis, sig, block } = parse_macro_input!(item as ItemFn);
+
+    let (should_panic, guard) = match expected {
+        Some(expected) => (
+            quote! { #[should_panic(expected = #expected)] },
+            quote! { ::hushed_panic::hush_matching(#expected) },
+        ),
+        None => (quote! {}, quote! { ::hushed_panic::hush_this_test() }),
+    };
+
+    let expanded = quote! {
+        #[test]
+        #should_panic
+        #(#attrs)*
+        #vis #sig {
+            let _hushed = #guard;
+            #block
+        }
+    };
+
+    expanded.into()
+}
+
+/// Marks a function as a test which must panic with a hushed
+/// panic on the test's thread.
+///
+/// With `expected = "..."`, only panics whose message contains
+/// the string are hushed, and the test fails if it panics with
+/// any other message.
+#[proc_macro_attribute]
+pub fn should_panic_quietly(args: TokenStream, item: TokenStream) -> TokenStream {
+    let expected = match parse_expected(args, "should_panic_quietly") {
+        Ok(expected) => expected,
+        Err(error) => return error.into_compile_error().into(),
+    };
+
+    let ItemFn { attrs, vis, sig, block } = parse_macro_input!(item as ItemFn);
+
+    let expected = match expected {
+        Some(expected) => quote! { ::std::option::Option::Some(#expected) },
+        None => quote! { ::std::option::Option::None },
+    };
+
+    let expanded = quote! {
+        #[test]
+        #(#attrs)*
+        #vis #sig {
+            ::hushed_panic::should_panic_quietly(#expected, move || #block)
+        }
+    };
+
+    expanded.into()
+}
@@ -0,0 +1,139 @@
+use crate::report::Reports;
+use crate::{HushLevel, LocationRule, PanicReport, Pattern};
+use parking_lot::Mutex;
+use std::panic::PanicHookInfo;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::ThreadId;
+
+/// A user supplied rule deciding whether to hush a panic.
+pub(crate) type Predicate = Arc<dyn Fn(&PanicHookInfo) -> bool + Send + Sync + 'static>;
+
+/// Identifies a single hush on a thread's stack of hushes.
+///
+/// Ids increase over time, so the most recently created of
+/// several frames has the greatest id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct FrameId(u64);
+
+impl FrameId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        FrameId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Decides which panics a frame hushes.
+#[derive(Clone)]
+pub(crate) enum Rule {
+    /// Hush every panic.
+    Always,
+    /// Hush panics whose message matches the pattern.
+    Message(Pattern),
+    /// Hush panics originating from a matching location.
+    Location(LocationRule),
+    /// Hush panics for which the predicate returns `true`.
+    Predicate(Predicate),
+    /// Hush panics on threads whose name matches the pattern.
+    ThreadName(Pattern),
+}
+
+impl Rule {
+    pub(crate) fn matches(&self, panic_info: &PanicHookInfo, message: &str) -> bool {
+        match self {
+            Rule::Always => true,
+            Rule::Message(pattern) => pattern.matches(message),
+            Rule::Location(rule) => panic_info.location().is_some_and(|location| rule.matches(location)),
+            Rule::Predicate(predicate) => predicate(panic_info),
+            Rule::ThreadName(pattern) => std::thread::current().name().is_some_and(|name| pattern.matches(name)),
+        }
+    }
+}
+
+/// One level of hushing on a thread, pushed by `hush_panic`,
+/// a `HushGuard` or a `hush` scope.
+///
+/// Frames inherited by spawned threads share their reports with
+/// the frame they were inherited from.
+pub(crate) struct Frame {
+    pub(crate) id: FrameId,
+    pub(crate) rule: Rule,
+    pub(crate) level: HushLevel,
+    /// Threads this frame never hushes, for global hushes.
+    pub(crate) exceptions: Vec<ThreadId>,
+    pub(crate) reports: Arc<Mutex<Reports>>,
+    /// Whether the frame was pushed by `hush_panic`, and so may be
+    /// popped by `unhush_panic`.
+    pub(crate) manual: bool,
+}
+
+impl Frame {
+    pub(crate) fn new(rule: Rule) -> Self {
+        Self {
+            id: FrameId::next(),
+            rule,
+            level: HushLevel::Silent,
+            exceptions: Vec::new(),
+            reports: Default::default(),
+            manual: false,
+        }
+    }
+
+    /// The most recent panic this frame hushed on the current
+    /// thread, ignoring those from threads which inherited it.
+    pub(crate) fn last_local_report(&self) -> Option<PanicReport> {
+        let thread_id = std::thread::current().id();
+
+        self.reports
+            .lock()
+            .iter()
+            .rev()
+            .find(|report| report.thread_id() == thread_id)
+            .cloned()
+    }
+
+    /// Creates a copy of this frame for another thread.
+    pub(crate) fn inherit(&self) -> Self {
+        Self {
+            id: self.id,
+            rule: self.rule.clone(),
+            level: self.level,
+            exceptions: self.exceptions.clone(),
+            reports: self.reports.clone(),
+            manual: false,
+        }
+    }
+}
+
+/// Copies the frames for the hook to evaluate, so that no borrow
+/// or lock is held while running user supplied rules.
+pub(crate) fn snapshot(frames: &[Frame]) -> Vec<Frame> {
+    frames.iter().map(Frame::inherit).collect()
+}
+
+/// Keeps only the frames which hush the panic.
+pub(crate) fn hushing(mut frames: Vec<Frame>, panic_info: &PanicHookInfo, message: &str) -> Vec<Frame> {
+    let excepted = |frame: &Frame| {
+        !frame.exceptions.is_empty() && frame.exceptions.contains(&std::thread::current().id())
+    };
+
+    frames.retain(|frame| !excepted(frame) && frame.rule.matches(panic_info, message));
+    frames
+}
+
+/// How loudly the panic is hushed: the level of the most
+/// recently created of the frames hushing it.
+pub(crate) fn level<'a>(frames: impl IntoIterator<Item = &'a Frame>) -> HushLevel {
+    frames
+        .into_iter()
+        .max_by_key(|frame| frame.id)
+        .map_or(HushLevel::Silent, |frame| frame.level)
+}
+
+/// Records `report` in each of the frames.
+pub(crate) fn record(frames: &[Frame], report: &PanicReport) {
+    for frame in frames {
+        frame.reports.lock().push(report.clone());
+    }
+}
@@ -0,0 +1,124 @@
+//!
+//! Hushing for futures, which may be polled on a different
+//! thread each time, such as tasks on a multi-threaded runtime.
+//!
+//! ```
+//! use hushed_panic::future::HushedFutureExt;
+//! use std::future::Future;
+//! use std::pin::pin;
+//! use std::task::{Context, Poll, Waker};
+//!
+//! let mut cx = Context::from_waker(Waker::noop());
+//!
+//! let mut task = pin!(async { panic!("quiet") }.catch_hushed());
+//! match task.as_mut().poll(&mut cx) {
+//!     Poll::Ready(Err(report)) => assert_eq!(report.message(), "quiet"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+
+use crate::frame::{Frame, FrameId, Rule};
+use crate::PanicReport;
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, UnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Extension methods to hush the panics of a future.
+pub trait HushedFutureExt: Future + Sized {
+    /// Hushes panics on whichever thread polls this future, for
+    /// the duration of each poll.
+    fn hushed(self) -> Hushed<Self> {
+        crate::hook::ensure_installed();
+
+        Hushed {
+            future: self,
+            frame: Frame::new(Rule::Always),
+        }
+    }
+
+    /// Hushes panics like [`hushed`](HushedFutureExt::hushed),
+    /// and catches them, resolving to the hushed panic's
+    /// [`PanicReport`].
+    fn catch_hushed(self) -> CatchHushed<Self>
+    where
+        Self: UnwindSafe,
+    {
+        CatchHushed { inner: self.hushed() }
+    }
+}
+
+impl<F: Future> HushedFutureExt for F {}
+
+/// A future which hushes panics while it is being polled.
+///
+/// Create one by calling [`HushedFutureExt::hushed`].
+pub struct Hushed<F> {
+    future: F,
+    frame: Frame,
+}
+
+impl<F> Hushed<F> {
+    /// Returns the panics which have been hushed while polling
+    /// this future, oldest first.
+    pub fn reports(&self) -> Vec<PanicReport> {
+        self.frame.reports.lock().to_vec()
+    }
+}
+
+impl<F: Future> Future for Hushed<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `self`, and
+        // `frame` is not structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        let _hushed = PollHush(crate::push_frame(this.frame.inherit()));
+        future.poll(cx)
+    }
+}
+
+/// A future which hushes and catches panics while it is being
+/// polled.
+///
+/// Create one by calling [`HushedFutureExt::catch_hushed`].
+pub struct CatchHushed<F> {
+    inner: Hushed<F>,
+}
+
+impl<F> CatchHushed<F> {
+    /// Returns the panics which have been hushed while polling
+    /// this future, oldest first.
+    pub fn reports(&self) -> Vec<PanicReport> {
+        self.inner.reports()
+    }
+}
+
+impl<F: Future + UnwindSafe> Future for CatchHushed<F> {
+    type Output = Result<F::Output, PanicReport>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `inner` is never moved out of `self`.
+        let inner = unsafe { self.map_unchecked_mut(|this| &mut this.inner) };
+        let frame = inner.frame.inherit();
+
+        match std::panic::catch_unwind(AssertUnwindSafe(|| inner.poll(cx))) {
+            Ok(poll) => poll.map(Ok),
+            Err(payload) => Poll::Ready(Err(frame
+                .last_local_report()
+                .unwrap_or_else(|| PanicReport::from_payload(&*payload)))),
+        }
+    }
+}
+
+/// Undoes the hush for a single poll, even if it panics.
+struct PollHush(FrameId);
+
+impl Drop for PollHush {
+    fn drop(&mut self) {
+        crate::remove_frame(self.0);
+    }
+}
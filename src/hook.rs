@@ -0,0 +1,241 @@
+use crate::husher_hook;
+use parking_lot::{const_mutex, Mutex};
+use std::panic::PanicHookInfo;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A panic hook, as taken from or given to `std::panic`.
+pub(crate) type BaseHook = dyn Fn(&PanicHookInfo) + Send + Sync + 'static;
+
+/// The husher as it is currently installed.
+struct Installed {
+    /// The hook which was set before the husher, and is restored
+    /// when it is uninstalled.
+    previous: Arc<Box<BaseHook>>,
+    /// Identifies this installation in `CURRENT`.
+    generation: usize,
+    /// The address of the installed husher, to recognise it when
+    /// taking the hook back from `std::panic`.
+    address: usize,
+}
+
+static HOOK: Mutex<Option<Installed>> = const_mutex(None);
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// The generation of the husher `std::panic` currently holds, or
+/// zero once it has been dropped.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static NEXT_GENERATION: AtomicUsize = AtomicUsize::new(1);
+
+fn hook_address(hook: &BaseHook) -> usize {
+    hook as *const BaseHook as *const () as usize
+}
+
+/// The state captured by an installed husher.
+///
+/// `std::panic` drops it when another hook replaces it without
+/// keeping hold of it, which marks the husher as no longer
+/// current. Hooks which take the husher and call it from their
+/// own keep it current.
+struct Husher {
+    generation: usize,
+    base: Arc<Box<BaseHook>>,
+}
+
+impl Drop for Husher {
+    fn drop(&mut self) {
+        let _ = CURRENT.compare_exchange(self.generation, 0, Ordering::AcqRel, Ordering::Acquire);
+    }
+}
+
+/// Installs the husher in place of `previous`, forwarding
+/// non-hushed panics to `base`.
+fn install(previous: Arc<Box<BaseHook>>, base: Arc<Box<BaseHook>>) -> Installed {
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    let husher = Husher { generation, base };
+    let hook: Box<BaseHook> = Box::new(move |panic_info| husher_hook(panic_info, &**husher.base));
+    let address = hook_address(&*hook);
+
+    // Set before the hook, so that dropping an older husher in
+    // `set_hook` does not clear it.
+    CURRENT.store(generation, Ordering::Release);
+    std::panic::set_hook(hook);
+
+    Installed { previous, generation, address }
+}
+
+/// Whether the husher is still held by `std::panic`, without
+/// touching the hook itself.
+fn is_current(installed: &Installed) -> bool {
+    CURRENT.load(Ordering::Acquire) == installed.generation
+}
+
+/// Installs the husher on top of the current hook, forwarding
+/// non-hushed panics to it.
+fn install_on_current() -> Installed {
+    let previous = Arc::new(std::panic::take_hook());
+
+    install(previous.clone(), previous)
+}
+
+/// Installs the husher on first use, and warns once if another
+/// hook has since replaced it.
+///
+/// Does nothing on a panicking thread, where the hook cannot be
+/// changed, and which may be running the husher itself.
+pub(crate) fn ensure_installed() {
+    if std::thread::panicking() {
+        return;
+    }
+
+    let mut hook = HOOK.lock();
+
+    match &*hook {
+        None => *hook = Some(install_on_current()),
+        Some(installed) if !is_current(installed) && !WARNED.swap(true, Ordering::Relaxed) => {
+            eprintln!(
+                "warning: the panic hook was replaced after `hushed_panic` installed its own, so panics \
+                 will not be hushed. Call `hushed_panic::reinstall()` to hush on top of the new hook."
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Whether `hushed_panic`'s hook is installed, and has not been
+/// replaced by another call to [`std::panic::set_hook`].
+///
+/// A hook which takes `hushed_panic`'s with
+/// [`std::panic::take_hook`] and forwards to it does not count as
+/// replacing it.
+/// ```
+/// let _guard = hushed_panic::hush_this_test();
+///
+/// let husher = std::panic::take_hook();
+/// std::panic::set_hook(Box::new(move |panic_info| husher(panic_info)));
+/// assert!(hushed_panic::is_installed());
+/// ```
+pub fn is_installed() -> bool {
+    CURRENT.load(Ordering::Acquire) != 0
+}
+
+/// Installs `hushed_panic`'s hook on top of whichever hook is
+/// currently set, if it has been replaced.
+///
+/// Non-hushed panics are then forwarded to the new hook.
+/// ```
+/// let _guard = hushed_panic::hush_this_test();
+///
+/// std::panic::set_hook(Box::new(|_| eprintln!("my own hook")));
+/// assert!(!hushed_panic::is_installed());
+///
+/// hushed_panic::reinstall();
+/// assert!(hushed_panic::is_installed());
+/// ```
+///
+/// # Panics
+///
+/// Panics if called from a panicking thread, like
+/// [`std::panic::set_hook`].
+pub fn reinstall() {
+    let mut hook = HOOK.lock();
+
+    if !hook.as_ref().is_some_and(is_current) {
+        *hook = Some(install_on_current());
+        WARNED.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Installs `hushed_panic`'s hook, forwarding non-hushed panics
+/// to `base` rather than to the hook it replaces.
+///
+/// If `hushed_panic`'s hook is already installed, it is kept
+/// and only where it forwards panics changes. Either way,
+/// [`uninstall`] restores the hook which was set before
+/// `hushed_panic`'s.
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static LOUD: AtomicUsize = AtomicUsize::new(0);
+/// hushed_panic::install_with(|_| {
+///     LOUD.fetch_add(1, Ordering::Relaxed);
+/// });
+///
+/// let _ = std::panic::catch_unwind(|| panic!("loud"));
+/// let _ = hushed_panic::hush(|| panic!("quiet"));
+/// assert_eq!(LOUD.load(Ordering::Relaxed), 1);
+/// ```
+///
+/// `base` is called without any of `hushed_panic`'s locks held,
+/// so it may hush and unhush, even while other threads panic:
+/// ```
+/// hushed_panic::install_with(|_| {
+///     let _guard = hushed_panic::hush_this_test();
+///     hushed_panic::hush_panic();
+///     hushed_panic::unhush_panic();
+/// });
+///
+/// let threads = (0..8)
+///     .map(|_| std::thread::spawn(|| panic!("loud")))
+///     .collect::<Vec<_>>();
+/// for thread in threads {
+///     assert!(thread.join().is_err());
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if called from a panicking thread, like
+/// [`std::panic::set_hook`].
+pub fn install_with<F: Fn(&PanicHookInfo) + Send + Sync + 'static>(base: F) {
+    let mut hook = HOOK.lock();
+
+    let previous = match hook.take() {
+        Some(installed) if is_current(&installed) => installed.previous,
+        _ => Arc::new(std::panic::take_hook()),
+    };
+
+    *hook = Some(install(previous, Arc::new(Box::new(base))));
+    WARNED.store(false, Ordering::Relaxed);
+}
+
+/// Removes `hushed_panic`'s hook, restoring the hook which was
+/// set before it was installed.
+///
+/// If another hook has replaced `hushed_panic`'s, or wraps it,
+/// that hook is left in place. Hushing anything afterwards
+/// installs `hushed_panic`'s hook again.
+/// ```
+/// let guard = hushed_panic::hush_this_test();
+/// assert!(hushed_panic::is_installed());
+/// drop(guard);
+///
+/// hushed_panic::uninstall();
+/// assert!(!hushed_panic::is_installed());
+/// ```
+///
+/// # Panics
+///
+/// Panics if called from a panicking thread, like
+/// [`std::panic::set_hook`].
+pub fn uninstall() {
+    let mut hook = HOOK.lock();
+    let installed = match hook.take() {
+        Some(installed) if is_current(&installed) => installed,
+        _ => return,
+    };
+
+    let current = std::panic::take_hook();
+    if hook_address(&*current) != installed.address {
+        std::panic::set_hook(current);
+        *hook = Some(installed);
+        return;
+    }
+
+    // Dropping the husher releases its handle on `previous`, so
+    // the original hook is restored as it was.
+    drop(current);
+    let previous = Arc::try_unwrap(installed.previous)
+        .unwrap_or_else(|previous| Box::new(move |panic_info| previous(panic_info)));
+    std::panic::set_hook(previous);
+}
@@ -0,0 +1,20 @@
+/// How much of a hushed panic is still printed to the
+/// [`Output`](crate::Output).
+///
+/// Hushed panics are recorded as [`PanicReport`](crate::PanicReport)s
+/// at every level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum HushLevel {
+    /// Print nothing.
+    #[default]
+    Silent,
+    /// Print a single line, such as
+    /// `thread 'main' panicked at src/main.rs:2:5: oh no`.
+    OneLine,
+    /// Print what the standard library's hook prints, without
+    /// a backtrace.
+    NoBacktrace,
+    /// Handle the panic as if it were not hushed, forwarding it
+    /// to the original hook.
+    Full,
+}
@@ -13,81 +13,1018 @@
 //!     drop(_x);
 //!     panic!(); // Would print normally!
 //! }
+//! # my_test();
 //! ```
 //!
+//! Or, equivalently, with the attribute macro:
+//! ```
+//! #[hushed_panic::hushed_test]
+//! #[should_panic]
+//! fn my_test() {
+//!     panic!(); // Won't print anything!
+//! }
+//!
+//! #[hushed_panic::hushed_test(expected = "index out of bounds")]
+//! fn my_other_test() {
+//!     let _ = [1, 2, 3][std::hint::black_box(4)];
+//! }
+//!
+//! // Fails unless the body panics with a hushed panic
+//! // containing the message.
+//! #[hushed_panic::should_panic_quietly(expected = "index out of bounds")]
+//! fn my_last_test() {
+//!     let _ = [1, 2, 3][std::hint::black_box(4)];
+//! }
+//! ```
+//!
+//! Hushed panics are not lost, they are recorded as
+//! [`PanicReport`]s which can be inspected through the guard:
+//! ```
+//! let guard = hushed_panic::hush_this_test();
+//! let _ = std::panic::catch_unwind(|| panic!("oh no"));
+//!
+//! let reports = guard.reports();
+//! assert_eq!(reports.len(), 1);
+//! assert_eq!(reports[0].message(), "oh no");
+//! assert_eq!(reports[0].location().unwrap().file(), file!());
+//! ```
+//!
+//! So that long-lived hushes such as [`hush_all`] don't grow
+//! without bound, each guard keeps only the last [`MAX_REPORTS`]
+//! reports and counts the ones it discards:
+//! ```
+//! use hushed_panic::MAX_REPORTS;
+//!
+//! let guard = hushed_panic::hush_this_test();
+//! for i in 0..=MAX_REPORTS {
+//!     let _ = std::panic::catch_unwind(|| panic!("{}", i));
+//! }
+//!
+//! let reports = guard.reports();
+//! assert_eq!(reports.len(), MAX_REPORTS);
+//! assert_eq!(reports[0].message(), "1");
+//! assert_eq!(guard.discarded_reports(), 1);
+//! ```
+//!
+//! Or, to hush and catch a single panic in one go:
+//! ```
+//! let report = hushed_panic::hush(|| panic!("oh no")).unwrap_err();
+//! assert_eq!(report.message(), "oh no");
+//!
+//! assert_eq!(hushed_panic::hush(|| 4).ok(), Some(4));
+//! ```
+//!
+//! # Other panic hooks
+//!
+//! The first time anything is hushed, `hushed_panic` takes the
+//! current panic hook and installs its own in its place, which
+//! forwards every panic it does not hush to the hook it took.
+//!
+//! Other hooks should therefore be installed before anything is
+//! hushed. If another hook is installed afterwards, it replaces
+//! `hushed_panic`'s and nothing is hushed anymore; a warning is
+//! printed the next time something is hushed, and
+//! [`reinstall`] installs `hushed_panic`'s hook again on top of
+//! the new one. Hooks which take `hushed_panic`'s with
+//! [`std::panic::take_hook`] and call it from their own do not
+//! replace it.
+//!
+//! [`install_with`] installs `hushed_panic`'s hook up front,
+//! forwarding non-hushed panics to a hook of your choosing rather
+//! than to whichever hook happened to be set, and [`uninstall`]
+//! restores the hook which was set before `hushed_panic`
+//! installed its own.
+//!
+//! Only panics which are forwarded as they happen reach the hook
+//! `hushed_panic` forwards to. Panics printed at a condensed
+//! [`HushLevel`], and reports printed by [`HushGuard::replay`] or
+//! [`HushGuard::replay_on_failure`], are formatted by
+//! `hushed_panic` in the standard library's format instead. A
+//! hook's own formatting, such as that of `color-eyre` or
+//! `human-panic`, is therefore not used for them: the original
+//! [`PanicHookInfo`] cannot be recreated to pass to it later.
+//!
 
-use once_cell::sync::OnceCell;
+use parking_lot::{const_mutex, Mutex};
+use std::collections::HashMap;
+use std::sync::LazyLock;
 use std::thread::ThreadId;
-use std::collections::HashSet;
-use parking_lot::Mutex;
-use std::panic::PanicInfo;
+use std::panic::{AssertUnwindSafe, PanicHookInfo, UnwindSafe};
+use std::sync::Arc;
 use std::marker::PhantomData;
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::io::Write;
 
-static HUSHED_THREADS: OnceCell<(Box<dyn Fn(&PanicInfo) + Send + Sync + 'static>, Mutex<HashSet<ThreadId>>)> = OnceCell::new();
+mod frame;
+pub mod future;
+mod hook;
+mod level;
+mod macros;
+mod output;
+mod pattern;
+mod report;
+pub mod thread;
+
+use frame::{Frame, FrameId, Rule};
+use hook::BaseHook;
+use report::Reports;
+pub use level::HushLevel;
+pub use output::{route_unhushed, set_output, Output, SharedBuffer};
+pub use hook::{install_with, is_installed, reinstall, uninstall};
+pub use pattern::{LocationRule, Pattern};
+pub use hushed_panic_macros::{hushed_test, should_panic_quietly};
+pub use report::{Location, PanicReport, MAX_REPORTS};
+
+thread_local! {
+    /// This thread's stack of hushes.
+    static FRAMES: ThreadFrames = const {
+        ThreadFrames {
+            frames: RefCell::new(Vec::new()),
+            local: Cell::new(false),
+            registered: Cell::new(None),
+            remote_checked: Cell::new(0),
+        }
+    };
+}
 
-/// Custom panic hook.
-fn husher_hook(panic_info: &PanicInfo) {
-    let thread_id = std::thread::current().id();
+/// Hushes placed on threads by other threads. Hushes of a thread's
+/// own are only kept in `FRAMES`, so hushing never locks this.
+static HUSHED_THREADS: LazyLock<Mutex<HashMap<ThreadId, Vec<Frame>>>> = LazyLock::new(Default::default);
+/// How many threads have hushes of their own.
+static LOCALLY_HUSHED: AtomicUsize = AtomicUsize::new(0);
+/// How many hushes from other threads are in `HUSHED_THREADS`, so
+/// that the hook only locks it while there are any.
+static REMOTE_HUSHES: AtomicUsize = AtomicUsize::new(0);
+/// Incremented each time a thread is hushed from another thread,
+/// so that threads only look for new remote hushes once.
+static REMOTE_GENERATION: AtomicUsize = AtomicUsize::new(1);
 
-    while HUSHED_THREADS.get().is_none() {
-        std::hint::spin_loop();
+/// Updates the hushes placed on a thread by other threads,
+/// removing its entry once there are none left.
+fn update_remote_frames<T>(thread_id: ThreadId, f: impl FnOnce(&mut Vec<Frame>) -> T) -> T {
+    let mut threads = HUSHED_THREADS.lock();
+    let frames = threads.entry(thread_id).or_default();
+    let result = f(frames);
+
+    if frames.is_empty() {
+        threads.remove(&thread_id);
+    }
+
+    result
+}
+
+/// A thread's stack of hushes, which keeps the thread counted in
+/// `LOCALLY_HUSHED` while it is non-empty.
+struct ThreadFrames {
+    frames: RefCell<Vec<Frame>>,
+    /// Whether the thread is counted in `LOCALLY_HUSHED`.
+    local: Cell<bool>,
+    /// Set once the thread may have an entry in `HUSHED_THREADS`,
+    /// which is removed when it exits.
+    registered: Cell<Option<ThreadId>>,
+    /// The `REMOTE_GENERATION` at which the thread was last found
+    /// not to be hushed from another thread.
+    remote_checked: Cell<usize>,
+}
+
+impl ThreadFrames {
+    fn modify<T>(&self, f: impl FnOnce(&mut Vec<Frame>) -> T) -> T {
+        let mut frames = self.frames.borrow_mut();
+        let result = f(&mut frames);
+
+        match (frames.is_empty(), self.local.get()) {
+            (false, false) => {
+                self.register();
+                LOCALLY_HUSHED.fetch_add(1, Ordering::Relaxed);
+                self.local.set(true);
+            }
+            (true, true) => {
+                LOCALLY_HUSHED.fetch_sub(1, Ordering::Relaxed);
+                self.local.set(false);
+            }
+            _ => {}
+        }
+
+        result
+    }
+
+    /// Makes sure the thread's entry in `HUSHED_THREADS` is removed
+    /// when it exits, returning the thread's id.
+    fn register(&self) -> ThreadId {
+        match self.registered.get() {
+            Some(thread_id) => thread_id,
+            None => {
+                let thread_id = std::thread::current().id();
+                self.registered.set(Some(thread_id));
+                thread_id
+            }
+        }
     }
+}
+
+/// Deregisters the thread when it exits, even if it leaked
+/// some of its hushes, or is still hushed from another thread.
+impl Drop for ThreadFrames {
+    fn drop(&mut self) {
+        if self.local.take() {
+            LOCALLY_HUSHED.fetch_sub(1, Ordering::Relaxed);
+        }
+
+        let thread_id = match self.registered.take() {
+            Some(thread_id) if REMOTE_HUSHES.load(Ordering::Acquire) != 0 => thread_id,
+            _ => return,
+        };
 
-    HUSHED_THREADS.get().map(move |(f, x)| {
-        let guard = x.lock();
-        if !guard.contains(&thread_id) {
-            f(panic_info);
+        if let Some(frames) = HUSHED_THREADS.lock().remove(&thread_id) {
+            REMOTE_HUSHES.fetch_sub(frames.len(), Ordering::Release);
         }
-    }).unwrap_or_else(|| println!("Something went wrong! Please report to `hushed_panic`'s github."));
+    }
+}
+
+/// Makes sure this thread is deregistered when it exits, even if
+/// it is only ever hushed from another thread.
+pub(crate) fn register_thread() {
+    let _ = FRAMES.try_with(ThreadFrames::register);
+}
+
+/// Snapshots the hushes placed on this thread by other threads.
+///
+/// Once the thread has been found not to be hushed, it only looks
+/// again after another remote hush was placed on some thread.
+fn remote_frames() -> Vec<Frame> {
+    if REMOTE_HUSHES.load(Ordering::Acquire) == 0 {
+        return Vec::new();
+    }
+
+    FRAMES
+        .try_with(|frames| {
+            let generation = REMOTE_GENERATION.load(Ordering::Acquire);
+            if frames.remote_checked.get() == generation {
+                return Vec::new();
+            }
+
+            let thread_id = frames.register();
+            match HUSHED_THREADS.lock().get(&thread_id) {
+                Some(remote) => frame::snapshot(remote),
+                None => {
+                    frames.remote_checked.set(generation);
+                    Vec::new()
+                }
+            }
+        })
+        .unwrap_or_default()
 }
 
-fn init_hushed_threads() -> (Box<dyn Fn(&PanicInfo) + 'static + Send + Sync>, Mutex<HashSet<ThreadId>>) {
-    let original = std::panic::take_hook();
-    std::panic::set_hook(Box::new(husher_hook));
+/// Hushes which apply to every thread. Only locked by the hook
+/// while `GLOBAL_HUSHES` is non-zero.
+static GLOBAL_FRAMES: Mutex<Vec<Frame>> = const_mutex(Vec::new());
+static GLOBAL_HUSHES: AtomicUsize = AtomicUsize::new(0);
 
-    (original, Default::default())
+thread_local! {
+    /// Whether the last panic on this thread was not hushed.
+    static LAST_PANIC_LOUD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Custom panic hook, forwarding panics it does not hush to
+/// `base`.
+///
+/// This only looks at this thread's own state unless a global or
+/// remote hush is active, so panicking threads never contend on
+/// a lock.
+/// Rules and `base` run without any borrow or lock held, so they
+/// may hush and unhush themselves.
+fn husher_hook(panic_info: &PanicHookInfo, base: &BaseHook) {
+    let mut local = with_frames(frame::snapshot).unwrap_or_default();
+    local.extend(remote_frames());
+    let global = match GLOBAL_HUSHES.load(Ordering::Acquire) {
+        0 => Vec::new(),
+        _ => frame::snapshot(&GLOBAL_FRAMES.lock()),
+    };
+
+    if local.is_empty() && global.is_empty() {
+        set_last_panic_loud(true);
+        forward(panic_info, base);
+        return;
+    }
+
+    let message = report::payload_message(panic_info.payload());
+    let local = frame::hushing(local, panic_info, &message);
+    let global = frame::hushing(global, panic_info, &message);
+
+    if local.is_empty() && global.is_empty() {
+        set_last_panic_loud(true);
+        forward(panic_info, base);
+        return;
+    }
+
+    set_last_panic_loud(false);
+    let report = PanicReport::capture(panic_info);
+    frame::record(&local, &report);
+    frame::record(&global, &report);
+
+    match frame::level(local.iter().chain(&global)) {
+        HushLevel::Silent => {}
+        HushLevel::Full => forward(panic_info, base),
+        level => {
+            let _ = output::write_line(format_args!("{}", report.at_level(level)));
+        }
+    }
+}
+
+/// Handles a panic which is not hushed, by forwarding it to
+/// `base` or writing it to the output.
+fn forward(panic_info: &PanicHookInfo, base: &BaseHook) {
+    if output::routes_unhushed() {
+        let _ = output::write_line(format_args!("{}", PanicReport::capture(panic_info)));
+    } else {
+        base(panic_info);
+    }
+}
+
+/// Runs `f` on this thread's stack of hushes, unless it is
+/// being modified or has been destroyed.
+fn with_frames<T>(f: impl FnOnce(&[Frame]) -> T) -> Option<T> {
+    FRAMES
+        .try_with(|frames| frames.frames.try_borrow().ok().map(|frames| f(&frames)))
+        .ok()
+        .flatten()
+}
+
+/// Records whether the panic the hook is handling escaped every
+/// hush on this thread.
+fn set_last_panic_loud(loud: bool) {
+    let _ = LAST_PANIC_LOUD.try_with(|last| last.set(loud));
+}
+
+/// Whether this thread is unwinding from a panic which was not
+/// hushed.
+fn unwinding_loudly() -> bool {
+    std::thread::panicking() && LAST_PANIC_LOUD.try_with(Cell::get).unwrap_or(true)
+}
+
+/// Pushes a new level of hushing onto this thread's stack.
+pub(crate) fn push_frame(frame: Frame) -> FrameId {
+    let id = frame.id;
+
+    FRAMES.with(|frames| frames.modify(|frames| frames.push(frame)));
+
+    id
+}
+
+/// Removes the level of hushing identified by `id` from this
+/// thread's stack, wherever it is in the stack.
+pub(crate) fn remove_frame(id: FrameId) -> Option<Frame> {
+    FRAMES
+        .try_with(|frames| {
+            frames.modify(|frames| {
+                let index = frames.iter().rposition(|frame| frame.id == id)?;
+
+                Some(frames.remove(index))
+            })
+        })
+        .ok()
+        .flatten()
+}
+
+/// Copies this thread's stack of hushes, for a thread it spawns
+/// to inherit.
+pub(crate) fn inherit_frames() -> Vec<Frame> {
+    with_frames(|frames| frames.iter().map(Frame::inherit).collect()).unwrap_or_default()
 }
 
 /// Hushes panics for this thread.
+///
+/// Hushes nest: each call must be paired with a call to
+/// `unhush_panic` for panics on this thread to be loud again.
 pub fn hush_panic() {
-    let (_, threads) = HUSHED_THREADS.get_or_init(init_hushed_threads);
+    hook::ensure_installed();
+    push_frame(Frame {
+        manual: true,
+        ..Frame::new(Rule::Always)
+    });
+}
+
+/// Un-hushes panics on this thread, undoing the most recent
+/// call to `hush_panic`.
+///
+/// Hushes from guards and `hush` scopes are left in place.
+/// Returns whether such a call was undone.
+/// ```
+/// let _guard = hushed_panic::hush_this_test();
+/// hushed_panic::hush_panic();
+///
+/// assert!(hushed_panic::unhush_panic());
+/// assert!(!hushed_panic::unhush_panic());
+/// assert!(std::panic::catch_unwind(|| panic!("Still hushed")).is_err());
+/// ```
+pub fn unhush_panic() -> bool {
+    FRAMES.with(|frames| {
+        frames.modify(|frames| {
+            frames
+                .iter()
+                .rposition(|frame| frame.manual)
+                .map(|index| frames.remove(index))
+                .is_some()
+        })
+    })
+}
+
+/// Returns how many threads are currently hushed, by hushes of
+/// their own or from another thread, for diagnostics.
+///
+/// Threads are counted until their last hush is undone, or until
+/// they exit, even if a guard was leaked. A thread hushed both by
+/// itself and from another thread is counted twice.
+/// ```
+/// let before = hushed_panic::hushed_thread_count();
+///
+/// let guard = hushed_panic::hush_this_test();
+/// assert_eq!(hushed_panic::hushed_thread_count(), before + 1);
+/// drop(guard);
+///
+/// std::thread::spawn(|| std::mem::forget(hushed_panic::hush_this_test()))
+///     .join()
+///     .unwrap();
+///
+/// assert_eq!(hushed_panic::hushed_thread_count(), before);
+/// ```
+///
+/// A thread hushed only from another thread is deregistered when
+/// it exits if it has panicked since, was spawned with
+/// [`thread::spawn`], or hushed itself. Otherwise, it is counted
+/// until its [`ThreadHushGuard`] is dropped.
+/// ```
+/// use std::sync::mpsc;
+///
+/// let before = hushed_panic::hushed_thread_count();
+///
+/// let (sender, receiver) = mpsc::channel::<()>();
+/// let worker = std::thread::spawn(move || {
+///     receiver.recv().unwrap();
+///     panic!("quiet");
+/// });
+///
+/// std::mem::forget(hushed_panic::hush_thread_handle(worker.thread()));
+/// sender.send(()).unwrap();
+/// assert!(worker.join().is_err());
+///
+/// assert_eq!(hushed_panic::hushed_thread_count(), before);
+/// ```
+pub fn hushed_thread_count() -> usize {
+    LOCALLY_HUSHED.load(Ordering::Relaxed) + HUSHED_THREADS.lock().len()
+}
+
+/// Hushes panics on the thread identified by `thread_id`, from
+/// any thread, while the returned guard is alive.
+///
+/// This is for threads whose code cannot hush itself, such as
+/// a library's worker threads.
+/// ```
+/// use std::sync::mpsc;
+///
+/// let (sender, receiver) = mpsc::channel::<()>();
+/// let worker = std::thread::spawn(move || {
+///     receiver.recv().unwrap();
+///     panic!("quiet");
+/// });
+///
+/// let guard = hushed_panic::hush_thread(worker.thread().id());
+/// sender.send(()).unwrap();
+/// assert!(worker.join().is_err());
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+pub fn hush_thread(thread_id: ThreadId) -> ThreadHushGuard {
+    hook::ensure_installed();
+
+    let frame = Frame::new(Rule::Always);
+    let guard = ThreadHushGuard {
+        id: frame.id,
+        thread_id,
+        reports: frame.reports.clone(),
+    };
 
-    let thread_id = std::thread::current().id();
+    update_remote_frames(thread_id, |frames| frames.push(frame));
+    REMOTE_HUSHES.fetch_add(1, Ordering::Release);
+    REMOTE_GENERATION.fetch_add(1, Ordering::Release);
 
-    threads.lock().insert(thread_id);
+    guard
 }
 
-/// Un-hushes panics on this thread.
+/// Hushes panics on `thread`, from any thread, while the returned
+/// guard is alive, like [`hush_thread`].
+pub fn hush_thread_handle(thread: &std::thread::Thread) -> ThreadHushGuard {
+    hush_thread(thread.id())
+}
+
+/// Runs `f` with panics hushed on this thread, catching
+/// any panic it raises.
 ///
-/// Returns whether the panic was hushed previously.
-pub fn unhush_panic() -> bool {
-    let thread_id = std::thread::current().id();
+/// The hush state from before the call is restored
+/// afterwards. If `f` panics, the hushed panic is returned
+/// as a [`PanicReport`].
+pub fn hush<F: FnOnce() -> T + UnwindSafe, T>(f: F) -> Result<T, PanicReport> {
+    hook::ensure_installed();
+    let id = push_frame(Frame::new(Rule::Always));
+
+    let result = std::panic::catch_unwind(f);
+
+    let report = remove_frame(id).and_then(|frame| frame.last_local_report());
+
+    result.map_err(|payload| report.unwrap_or_else(|| PanicReport::from_payload(&*payload)))
+}
+
+/// Runs `f`, checking that it panics on this thread with a
+/// hushed panic, and panicking loudly otherwise.
+///
+/// With an `expected` message, only panics containing it are
+/// hushed, and `f` must panic with such a message.
+///
+/// This is what `#[should_panic_quietly]` tests expand to.
+/// ```
+/// hushed_panic::should_panic_quietly(Some("out of bounds"), || {
+///     let _ = [1, 2, 3][std::hint::black_box(4)];
+/// });
+/// ```
+///
+/// ```should_panic
+/// hushed_panic::should_panic_quietly(None, || {});
+/// ```
+#[track_caller]
+pub fn should_panic_quietly<F: FnOnce() -> T, T>(expected: Option<&str>, f: F) {
+    let rule = match expected {
+        Some(expected) => Rule::Message(expected.into()),
+        None => Rule::Always,
+    };
+    hook::ensure_installed();
+    let id = push_frame(Frame::new(rule));
+
+    let result = std::panic::catch_unwind(AssertUnwindSafe(f));
 
-    let val = HUSHED_THREADS.get_or_init(init_hushed_threads).1.lock().remove(&thread_id);
+    let report = remove_frame(id).and_then(|frame| frame.last_local_report());
 
-    val
+    let payload = match result {
+        Ok(_) => panic!("test did not panic as expected"),
+        Err(payload) => payload,
+    };
+
+    let message = report::payload_message(&*payload);
+    if report.is_none_or(|report| report.message() != message) {
+        panic!(
+            "test panicked with an unexpected message\n panic message: {:?}\n expected substring: {:?}",
+            message,
+            expected.unwrap_or_default(),
+        );
+    }
 }
 
-/// Returns a guard which will call `unhush_panic`
-/// after it is dropped.
+/// Returns a guard which will undo its hush after it
+/// is dropped.
+///
+/// Guards nest, so an inner guard being dropped leaves
+/// the thread hushed for as long as an outer one lives.
 ///
 /// Use it as such:
 /// ```norun
-/// let _ = hush_this_test();
+/// let _x = hush_this_test();
+/// ```
+///
+/// ```
+/// let outer = hushed_panic::hush_this_test();
+/// let inner = hushed_panic::hush_this_test();
+/// drop(inner);
+///
+/// // Still hushed by `outer`.
+/// let _ = std::panic::catch_unwind(|| panic!("quiet"));
+/// assert_eq!(outer.reports().len(), 1);
 /// ```
 pub fn hush_this_test() -> HushGuard {
-    hush_panic();
-    HushGuard { internal: PhantomData }
+    HushGuard::new(Frame::new(Rule::Always))
 }
 
-/// When this `struct` is dropped, the current thread's
-/// panic is unhushed.
+/// Returns a guard which hushes only the panics on this
+/// thread whose message matches `pattern`, while it is alive.
 ///
-/// Create an instance of this by calling `hush_this_test`.
-pub struct HushGuard { internal: PhantomData<*const ()> }
+/// Any other panic still goes to the original hook.
+/// ```
+/// let guard = hushed_panic::hush_matching("index out of bounds");
+/// let _ = std::panic::catch_unwind(|| [1, 2, 3][std::hint::black_box(4)]);
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+pub fn hush_matching(pattern: impl Into<Pattern>) -> HushGuard {
+    HushGuard::new(Frame::new(Rule::Message(pattern.into())))
+}
+
+/// Returns a guard which hushes only the panics on this
+/// thread originating from a location matching `rule`, while
+/// it is alive.
+///
+/// Any other panic still goes to the original hook.
+/// ```
+/// use hushed_panic::LocationRule;
+///
+/// let guard = hushed_panic::hush_at(LocationRule::file(file!()));
+/// let _ = std::panic::catch_unwind(|| panic!("from this file"));
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+pub fn hush_at(rule: LocationRule) -> HushGuard {
+    HushGuard::new(Frame::new(Rule::Location(rule)))
+}
+
+/// Returns a guard which hushes the panics on this thread for
+/// which `predicate` returns `true`, while it is alive.
+///
+/// Any other panic still goes to the original hook.
+/// ```
+/// let guard = hushed_panic::hush_if(|info| info.payload().is::<u32>());
+/// let _ = std::panic::catch_unwind(|| std::panic::panic_any(4_u32));
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+///
+/// The predicate may itself hush and unhush:
+/// ```
+/// let guard = hushed_panic::hush_if(|_| hushed_panic::hush(|| true).unwrap_or(false));
+/// let _ = std::panic::catch_unwind(|| panic!("quiet"));
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+pub fn hush_if<F: Fn(&PanicHookInfo) -> bool + Send + Sync + 'static>(predicate: F) -> HushGuard {
+    HushGuard::new(Frame::new(Rule::Predicate(Arc::new(predicate))))
+}
+
+/// Returns a guard which hushes the panics on every thread for
+/// which `predicate` returns `true`, while it is alive.
+///
+/// This hush is not tied to a thread, so the guard may be sent
+/// elsewhere.
+/// ```
+/// let guard = hushed_panic::hush_if_global(|info| {
+///     std::thread::current().name() == Some("worker")
+/// });
+///
+/// let _ = std::thread::Builder::new()
+///     .name("worker".to_owned())
+///     .spawn(|| panic!("quiet"))
+///     .unwrap()
+///     .join();
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+pub fn hush_if_global<F: Fn(&PanicHookInfo) -> bool + Send + Sync + 'static>(predicate: F) -> GlobalHushGuard {
+    GlobalHushGuard::new(Frame::new(Rule::Predicate(Arc::new(predicate))))
+}
+
+/// Returns a guard which hushes the panics on every thread in
+/// the process, while it is alive.
+///
+/// Threads may be kept loud with
+/// [`except_current`](GlobalHushGuard::except_current) and
+/// [`except`](GlobalHushGuard::except).
+/// ```
+/// let guard = hushed_panic::hush_all().except_current();
+///
+/// let threads = (0..4)
+///     .map(|_| std::thread::spawn(|| panic!("quiet")))
+///     .collect::<Vec<_>>();
+/// for thread in threads {
+///     assert!(thread.join().is_err());
+/// }
+///
+/// assert_eq!(guard.reports().len(), 4);
+/// ```
+pub fn hush_all() -> GlobalHushGuard {
+    GlobalHushGuard::new(Frame::new(Rule::Always))
+}
+
+/// Returns a guard which hushes the panics on every thread whose
+/// name matches `glob`, while it is alive.
+///
+/// Names are matched when a thread panics, so this covers
+/// threads which do not exist yet, such as those of a thread
+/// pool, or those libtest runs each test on. Unnamed threads are
+/// never hushed. See [`Pattern::Glob`] for the syntax.
+/// ```
+/// let guard = hushed_panic::hush_threads_named("worker-*");
+///
+/// let _ = std::thread::Builder::new()
+///     .name("worker-1".to_owned())
+///     .spawn(|| panic!("quiet"))
+///     .unwrap()
+///     .join();
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+pub fn hush_threads_named(glob: &str) -> GlobalHushGuard {
+    GlobalHushGuard::new(Frame::new(Rule::ThreadName(Pattern::glob(glob))))
+}
+
+/// When this `struct` is dropped, the hush it created on
+/// the current thread is undone.
+///
+/// Create an instance of this by calling `hush_this_test`,
+/// `hush_matching`, `hush_at` or `hush_if`.
+pub struct HushGuard {
+    id: FrameId,
+    replay_on_failure: bool,
+    failed: Cell<bool>,
+    internal: PhantomData<*const ()>,
+}
+
+impl HushGuard {
+    fn new(frame: Frame) -> Self {
+        hook::ensure_installed();
+
+        Self {
+            id: push_frame(frame),
+            replay_on_failure: false,
+            failed: Cell::new(false),
+            internal: PhantomData,
+        }
+    }
+
+    /// Sets how much of the panics this guard hushes is still
+    /// printed, [`HushLevel::Silent`] by default.
+    ///
+    /// When several hushes apply to a panic, the most recently
+    /// created one decides.
+    /// ```
+    /// use hushed_panic::HushLevel;
+    ///
+    /// let guard = hushed_panic::hush_this_test().level(HushLevel::OneLine);
+    /// // Prints `thread 'main' panicked at <file>:<line>:<column>: condensed`.
+    /// let _ = std::panic::catch_unwind(|| panic!("condensed"));
+    /// assert_eq!(guard.reports().len(), 1);
+    /// ```
+    pub fn level(self, level: HushLevel) -> Self {
+        FRAMES.with(|frames| {
+            frames.modify(|frames| {
+                if let Some(frame) = frames.iter_mut().find(|frame| frame.id == self.id) {
+                    frame.level = level;
+                }
+            })
+        });
+
+        self
+    }
+
+    /// Makes this guard buffer the panics it hushes, and print
+    /// them when it is dropped if the thread failed.
+    ///
+    /// The thread has failed if it is unwinding from a panic
+    /// which was not hushed, or if [`fail`] was called. Expected
+    /// panics escaping the guard's scope are hushed, so do not
+    /// count. Panics are printed as with [`replay`].
+    ///
+    /// ```
+    /// use hushed_panic::{Output, SharedBuffer};
+    ///
+    /// let buffer = SharedBuffer::new();
+    /// hushed_panic::set_output(Output::from(buffer.clone()));
+    ///
+    /// let guard = hushed_panic::hush_this_test().replay_on_failure();
+    /// let _ = std::panic::catch_unwind(|| panic!("printed on failure"));
+    /// drop(guard);
+    /// // Nothing is printed, since the thread did not fail.
+    /// assert!(buffer.contents().is_empty());
+    ///
+    /// // Nor when the expected panic escapes the guard's scope.
+    /// let _ = std::panic::catch_unwind(|| {
+    ///     let _guard = hushed_panic::hush_this_test().replay_on_failure();
+    ///     panic!("expected");
+    /// });
+    /// assert!(buffer.contents().is_empty());
+    ///
+    /// let guard = hushed_panic::hush_this_test().replay_on_failure();
+    /// let _ = std::panic::catch_unwind(|| panic!("printed on failure"));
+    /// guard.fail();
+    /// drop(guard);
+    /// assert!(buffer.to_string_lossy().contains("printed on failure"));
+    /// ```
+    ///
+    /// [`fail`]: HushGuard::fail
+    /// [`replay`]: HushGuard::replay
+    pub fn replay_on_failure(mut self) -> Self {
+        self.replay_on_failure = true;
+        self
+    }
+
+    /// Marks the thread as failed, so that a guard made with
+    /// [`replay_on_failure`](HushGuard::replay_on_failure)
+    /// prints its hushed panics when dropped.
+    pub fn fail(&self) {
+        self.failed.set(true);
+    }
+
+    /// Prints the panics this guard has hushed so far to the
+    /// [`Output`], oldest first.
+    ///
+    /// Panics are printed the way the standard library's hook
+    /// prints them, since the original [`PanicHookInfo`] cannot
+    /// be recreated to pass to the original hook.
+    pub fn replay(&self) {
+        write_reports(&self.reports());
+    }
+
+    /// Writes the panics this guard has hushed so far to
+    /// `writer`, oldest first, like [`replay`](HushGuard::replay).
+    /// ```
+    /// let guard = hushed_panic::hush_this_test();
+    /// let _ = std::panic::catch_unwind(|| panic!("oh no"));
+    ///
+    /// let mut output = Vec::new();
+    /// guard.replay_to(&mut output).unwrap();
+    /// let output = String::from_utf8(output).unwrap();
+    /// assert!(output.contains("oh no"));
+    /// ```
+    pub fn replay_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        for report in self.reports() {
+            writeln!(writer, "{}", report)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Whether this guard has hushed any panics yet.
+    /// ```
+    /// let guard = hushed_panic::hush_this_test();
+    /// assert!(!guard.has_panicked());
+    ///
+    /// let _ = std::panic::catch_unwind(|| panic!());
+    /// assert!(guard.has_panicked());
+    /// ```
+    pub fn has_panicked(&self) -> bool {
+        !self.reports().is_empty()
+    }
+
+    /// Returns the panics which this guard has hushed on
+    /// this thread, and on any threads which inherited it,
+    /// oldest first. At most [`MAX_REPORTS`] are kept.
+    pub fn reports(&self) -> Vec<PanicReport> {
+        self.frame_reports()
+            .map(|reports| reports.lock().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// How many of the panics this guard has hushed were
+    /// discarded to keep at most [`MAX_REPORTS`] reports.
+    pub fn discarded_reports(&self) -> usize {
+        self.frame_reports()
+            .map(|reports| reports.lock().discarded())
+            .unwrap_or_default()
+    }
+
+    fn frame_reports(&self) -> Option<Arc<Mutex<Reports>>> {
+        with_frames(|frames| {
+            frames
+                .iter()
+                .find(|frame| frame.id == self.id)
+                .map(|frame| frame.reports.clone())
+        })
+        .flatten()
+    }
+}
 
 impl Drop for HushGuard {
     fn drop(&mut self) {
-        unhush_panic();
+        let frame = remove_frame(self.id);
+
+        let failed = self.failed.get() || unwinding_loudly();
+        if let (true, true, Some(frame)) = (self.replay_on_failure, failed, frame) {
+            write_reports(frame.reports.lock().iter());
+        }
+    }
+}
+
+/// Writes each report to the output in the standard library's
+/// format.
+fn write_reports<'a>(reports: impl IntoIterator<Item = &'a PanicReport>) {
+    for report in reports {
+        let _ = output::write_line(format_args!("{}", report));
+    }
+}
+
+/// When this `struct` is dropped, the hush it created for
+/// every thread is undone.
+///
+/// Create an instance of this by calling `hush_all`,
+/// `hush_if_global` or `hush_threads_named`.
+pub struct GlobalHushGuard { id: FrameId }
+
+impl GlobalHushGuard {
+    fn new(frame: Frame) -> Self {
+        hook::ensure_installed();
+
+        let id = frame.id;
+        GLOBAL_FRAMES.lock().push(frame);
+        GLOBAL_HUSHES.fetch_add(1, Ordering::Release);
+
+        Self { id }
+    }
+
+    /// Sets how much of the panics this guard hushes is still
+    /// printed, like [`HushGuard::level`].
+    pub fn level(self, level: HushLevel) -> Self {
+        if let Some(frame) = GLOBAL_FRAMES.lock().iter_mut().find(|frame| frame.id == self.id) {
+            frame.level = level;
+        }
+
+        self
+    }
+
+    /// Keeps panics on the current thread loud, despite this
+    /// guard.
+    pub fn except_current(self) -> Self {
+        self.except(std::thread::current().id())
+    }
+
+    /// Keeps panics on the thread identified by `thread_id` loud,
+    /// despite this guard.
+    pub fn except(self, thread_id: ThreadId) -> Self {
+        if let Some(frame) = GLOBAL_FRAMES.lock().iter_mut().find(|frame| frame.id == self.id) {
+            frame.exceptions.push(thread_id);
+        }
+
+        self
+    }
+
+    /// Returns the panics which this guard has hushed on any
+    /// thread, oldest first. At most [`MAX_REPORTS`] are kept.
+    pub fn reports(&self) -> Vec<PanicReport> {
+        self.frame_reports()
+            .map(|reports| reports.lock().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// How many of the panics this guard has hushed were
+    /// discarded to keep at most [`MAX_REPORTS`] reports.
+    pub fn discarded_reports(&self) -> usize {
+        self.frame_reports()
+            .map(|reports| reports.lock().discarded())
+            .unwrap_or_default()
+    }
+
+    fn frame_reports(&self) -> Option<Arc<Mutex<Reports>>> {
+        GLOBAL_FRAMES
+            .lock()
+            .iter()
+            .find(|frame| frame.id == self.id)
+            .map(|frame| frame.reports.clone())
+    }
+}
+
+impl Drop for GlobalHushGuard {
+    fn drop(&mut self) {
+        GLOBAL_FRAMES.lock().retain(|frame| frame.id != self.id);
+        GLOBAL_HUSHES.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// When this `struct` is dropped, the hush it created on
+/// another thread is undone.
+///
+/// Create an instance of this by calling `hush_thread` or
+/// `hush_thread_handle`.
+pub struct ThreadHushGuard {
+    id: FrameId,
+    thread_id: ThreadId,
+    reports: Arc<Mutex<Reports>>,
+}
+
+impl ThreadHushGuard {
+    /// Sets how much of the panics this guard hushes is still
+    /// printed, like [`HushGuard::level`].
+    pub fn level(self, level: HushLevel) -> Self {
+        if let Some(frames) = HUSHED_THREADS.lock().get_mut(&self.thread_id) {
+            if let Some(frame) = frames.iter_mut().find(|frame| frame.id == self.id) {
+                frame.level = level;
+            }
+        }
+
+        self
+    }
+
+    /// The thread this guard hushes.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// Returns the panics which this guard has hushed, oldest
+    /// first. At most [`MAX_REPORTS`] are kept.
+    pub fn reports(&self) -> Vec<PanicReport> {
+        self.reports.lock().to_vec()
+    }
+
+    /// How many of the panics this guard has hushed were
+    /// discarded to keep at most [`MAX_REPORTS`] reports.
+    pub fn discarded_reports(&self) -> usize {
+        self.reports.lock().discarded()
+    }
+}
+
+impl Drop for ThreadHushGuard {
+    fn drop(&mut self) {
+        let removed = update_remote_frames(self.thread_id, |frames| {
+            let before = frames.len();
+            frames.retain(|frame| frame.id != self.id);
+            before - frames.len()
+        });
+        REMOTE_HUSHES.fetch_sub(removed, Ordering::Release);
     }
 }
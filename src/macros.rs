@@ -0,0 +1,69 @@
+/// Asserts that an expression panics, hushing the panic.
+///
+/// With a second argument, also asserts that the panic's
+/// message contains it. Evaluates to the panic's
+/// [`PanicReport`](crate::PanicReport).
+/// ```
+/// use hushed_panic::assert_panics;
+///
+/// let values = [1, 2, 3];
+/// assert_panics!(values[std::hint::black_box(4)]);
+/// let report = assert_panics!(values[std::hint::black_box(4)], "out of bounds");
+/// assert_eq!(report.location().unwrap().line(), line!() - 1);
+/// ```
+///
+/// ```should_panic
+/// hushed_panic::assert_panics!(1 + 1);
+/// ```
+#[macro_export]
+macro_rules! assert_panics {
+    ($expr:expr $(,)?) => {
+        match $crate::hush(::std::panic::AssertUnwindSafe(|| {
+            let _ = $expr;
+        })) {
+            ::std::result::Result::Ok(()) => ::std::panic!(
+                "assertion failed: `{}` did not panic",
+                ::std::stringify!($expr),
+            ),
+            ::std::result::Result::Err(report) => report,
+        }
+    };
+    ($expr:expr, $expected:expr $(,)?) => {{
+        let report = $crate::assert_panics!($expr);
+        let expected: &str = &$expected;
+        if !report.message().contains(expected) {
+            ::std::panic!(
+                "assertion failed: `{}` panicked with an unexpected message\n panic message: {:?}\n expected substring: {:?}",
+                ::std::stringify!($expr),
+                report.message(),
+                expected,
+            );
+        }
+        report
+    }};
+}
+
+/// Asserts that an expression panics, hushing the panic, and
+/// passes its [`PanicReport`](crate::PanicReport) to a closure
+/// for further checks.
+/// ```
+/// use hushed_panic::assert_panics_with;
+///
+/// let expected = "`None`";
+/// assert_panics_with!(Option::<u8>::None.unwrap(), |report| {
+///     assert!(report.message().contains(expected));
+///     assert_eq!(report.location().unwrap().file(), file!());
+/// });
+/// ```
+#[macro_export]
+macro_rules! assert_panics_with {
+    ($expr:expr, $check:expr $(,)?) => {{
+        fn check<F: ::std::ops::FnOnce(&$crate::PanicReport)>(report: &$crate::PanicReport, check: F) {
+            check(report)
+        }
+
+        let report = $crate::assert_panics!($expr);
+        check(&report, $check);
+        report
+    }};
+}
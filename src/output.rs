@@ -0,0 +1,151 @@
+use parking_lot::{const_mutex, Mutex};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Where `hushed_panic` writes the panics it formats itself.
+///
+/// These are condensed panics (see [`HushLevel`](crate::HushLevel)),
+/// replayed panics (see [`HushGuard::replay`](crate::HushGuard::replay)),
+/// and, with [`route_unhushed`], panics which are not hushed.
+pub enum Output {
+    /// The standard error. Errors writing to it are ignored
+    /// rather than panicking like `eprint!`, which also means
+    /// the output is not captured by the test harness.
+    Stderr,
+    /// The standard output. Errors writing to it are ignored
+    /// rather than panicking like `print!`, which also means
+    /// the output is not captured by the test harness.
+    Stdout,
+    /// Any writer, such as a file or a [`SharedBuffer`].
+    Writer(Box<dyn Write + Send>),
+}
+
+impl Output {
+    /// Writes to any writer.
+    pub fn writer<W: Write + Send + 'static>(writer: W) -> Self {
+        Output::Writer(Box::new(writer))
+    }
+
+    /// Appends to the file at `path`, creating it if needed.
+    pub fn file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Output::writer(file))
+    }
+}
+
+impl From<SharedBuffer> for Output {
+    fn from(buffer: SharedBuffer) -> Self {
+        Output::writer(buffer)
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Stderr => f.write_str("Stderr"),
+            Output::Stdout => f.write_str("Stdout"),
+            Output::Writer(_) => f.write_str("Writer(..)"),
+        }
+    }
+}
+
+/// An in-memory [`Output`] which can be read back through any
+/// of its clones.
+#[derive(Clone, Debug, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns everything written to the buffer so far.
+    pub fn contents(&self) -> Vec<u8> {
+        self.0.lock().clone()
+    }
+
+    /// Returns everything written to the buffer so far, as text.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock()).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+static OUTPUT: Mutex<Output> = const_mutex(Output::Stderr);
+static ROUTE_UNHUSHED: AtomicBool = AtomicBool::new(false);
+
+/// Sets where `hushed_panic` writes the panics it formats
+/// itself, [`Output::Stderr`] by default.
+/// ```
+/// use hushed_panic::{HushLevel, Output, SharedBuffer};
+///
+/// let buffer = SharedBuffer::new();
+/// hushed_panic::set_output(buffer.clone().into());
+///
+/// let guard = hushed_panic::hush_this_test().level(HushLevel::OneLine);
+/// let _ = std::panic::catch_unwind(|| panic!("condensed"));
+///
+/// assert!(buffer.to_string_lossy().ends_with(": condensed\n"));
+/// hushed_panic::set_output(Output::Stderr);
+/// ```
+pub fn set_output(output: Output) {
+    *OUTPUT.lock() = output;
+}
+
+/// Sets whether panics which are not hushed are written to the
+/// [`Output`], formatted like the standard library's hook does,
+/// instead of being forwarded to the original hook.
+///
+/// This is off by default.
+/// ```
+/// use hushed_panic::{Output, SharedBuffer};
+///
+/// let buffer = SharedBuffer::new();
+/// hushed_panic::set_output(buffer.clone().into());
+/// hushed_panic::route_unhushed(true);
+///
+/// let _ = std::panic::catch_unwind(|| panic!("to the buffer"));
+/// assert!(buffer.to_string_lossy().contains("to the buffer"));
+/// ```
+pub fn route_unhushed(route: bool) {
+    crate::hook::ensure_installed();
+    ROUTE_UNHUSHED.store(route, Ordering::Relaxed);
+}
+
+/// Whether panics which are not hushed go to the output.
+pub(crate) fn routes_unhushed() -> bool {
+    ROUTE_UNHUSHED.load(Ordering::Relaxed)
+}
+
+/// Writes `args` and a newline to the output.
+///
+/// This runs inside the panic hook, where a second panic would
+/// abort the process, so errors are returned rather than
+/// panicking like `eprintln!` does.
+pub(crate) fn write_line(args: fmt::Arguments<'_>) -> io::Result<()> {
+    match &mut *OUTPUT.lock() {
+        Output::Stderr => write_to(io::stderr().lock(), args),
+        Output::Stdout => write_to(io::stdout().lock(), args),
+        Output::Writer(writer) => write_to(writer, args),
+    }
+}
+
+fn write_to(mut writer: impl Write, args: fmt::Arguments<'_>) -> io::Result<()> {
+    writeln!(writer, "{}", args)?;
+    writer.flush()
+}
@@ -0,0 +1,142 @@
+use std::ops::RangeInclusive;
+use std::panic::Location;
+
+/// A pattern to match panic messages (and other strings)
+/// against.
+///
+/// A `&str` or `String` converts into a substring pattern.
+/// ```
+/// use hushed_panic::Pattern;
+///
+/// assert!(Pattern::from("out of bounds").matches("index out of bounds: the len is 3"));
+/// assert!(Pattern::glob("src/parser/*.rs").matches("src/parser/expr.rs"));
+/// assert!(!Pattern::glob("src/parser/*.rs").matches("src/lexer.rs"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Matches any string containing this one.
+    Substring(String),
+    /// Matches any string starting with this one.
+    Prefix(String),
+    /// Matches the whole string against a glob, where `*`
+    /// matches any run of characters and `?` matches any
+    /// single character.
+    Glob(String),
+}
+
+impl Pattern {
+    /// Creates a substring pattern.
+    pub fn substring(pattern: impl Into<String>) -> Self {
+        Pattern::Substring(pattern.into())
+    }
+
+    /// Creates a prefix pattern.
+    pub fn prefix(pattern: impl Into<String>) -> Self {
+        Pattern::Prefix(pattern.into())
+    }
+
+    /// Creates a glob pattern.
+    pub fn glob(pattern: impl Into<String>) -> Self {
+        Pattern::Glob(pattern.into())
+    }
+
+    /// Whether `text` matches this pattern.
+    pub fn matches(&self, text: &str) -> bool {
+        match self {
+            Pattern::Substring(pattern) => text.contains(pattern.as_str()),
+            Pattern::Prefix(pattern) => text.starts_with(pattern.as_str()),
+            Pattern::Glob(pattern) => glob_matches(pattern, text),
+        }
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(pattern: &str) -> Self {
+        Pattern::substring(pattern)
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(pattern: String) -> Self {
+        Pattern::Substring(pattern)
+    }
+}
+
+/// Decides whether a panic is hushed from where it
+/// originated.
+/// ```
+/// use hushed_panic::{LocationRule, Pattern};
+///
+/// // Anything in the parser.
+/// let _parser = LocationRule::file(Pattern::glob("src/parser/*.rs"));
+/// // A single known line.
+/// let _line = LocationRule::at("src/lib.rs", 42);
+/// // Everything from a noisy dependency.
+/// let _dependency = LocationRule::path_prefix("/home/me/.cargo/registry/src/");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocationRule {
+    file: Pattern,
+    lines: Option<RangeInclusive<u32>>,
+}
+
+impl LocationRule {
+    /// Matches panics in files matching `file`.
+    pub fn file(file: impl Into<Pattern>) -> Self {
+        Self { file: file.into(), lines: None }
+    }
+
+    /// Matches panics in files whose path starts with `prefix`.
+    pub fn path_prefix(prefix: impl Into<String>) -> Self {
+        Self::file(Pattern::prefix(prefix))
+    }
+
+    /// Matches panics on `line` of files matching `file`.
+    pub fn at(file: impl Into<Pattern>, line: u32) -> Self {
+        Self::file(file).lines(line..=line)
+    }
+
+    /// Restricts this rule to panics within `lines`.
+    pub fn lines(self, lines: RangeInclusive<u32>) -> Self {
+        Self { lines: Some(lines), ..self }
+    }
+
+    /// Whether a panic at `location` matches this rule.
+    pub fn matches(&self, location: &Location<'_>) -> bool {
+        self.file.matches(location.file())
+            && self.lines.as_ref().is_none_or(|lines| lines.contains(&location.line()))
+    }
+}
+
+/// Matches `text` against a glob containing `*` and `?`
+/// wildcards, backtracking to the last `*` on a mismatch.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
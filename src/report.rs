@@ -0,0 +1,223 @@
+use crate::HushLevel;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::fmt;
+use std::panic::PanicHookInfo;
+use std::sync::Arc;
+use std::thread::ThreadId;
+use std::time::SystemTime;
+
+/// An owned copy of a panic's [`std::panic::Location`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl Location {
+    /// The source file the panic originated from.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The line the panic originated from.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column the panic originated from.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl<'a> From<&std::panic::Location<'a>> for Location {
+    fn from(location: &std::panic::Location<'a>) -> Self {
+        Self {
+            file: location.file().to_owned(),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// How many reports each hush keeps. Once it has hushed more
+/// panics, the oldest reports are discarded and only counted.
+pub const MAX_REPORTS: usize = 1000;
+
+/// The reports recorded by a hush, newest last.
+#[derive(Default)]
+pub(crate) struct Reports {
+    kept: VecDeque<PanicReport>,
+    discarded: usize,
+}
+
+impl Reports {
+    /// Records `report`, discarding the oldest report if there
+    /// are already [`MAX_REPORTS`] of them.
+    pub(crate) fn push(&mut self, report: PanicReport) {
+        if self.kept.len() == MAX_REPORTS {
+            self.kept.pop_front();
+            self.discarded += 1;
+        }
+
+        self.kept.push_back(report);
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = &PanicReport> {
+        self.kept.iter()
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<PanicReport> {
+        self.kept.iter().cloned().collect()
+    }
+
+    /// How many reports were discarded to stay within
+    /// [`MAX_REPORTS`].
+    pub(crate) fn discarded(&self) -> usize {
+        self.discarded
+    }
+}
+
+/// A record of a panic which was hushed.
+#[derive(Clone, Debug)]
+pub struct PanicReport {
+    message: String,
+    location: Option<Location>,
+    thread_name: Option<String>,
+    thread_id: ThreadId,
+    timestamp: SystemTime,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+impl PanicReport {
+    /// Records the panic described by `panic_info` on the current thread.
+    pub(crate) fn capture(panic_info: &PanicHookInfo) -> Self {
+        let thread = std::thread::current();
+        let backtrace = Backtrace::capture();
+
+        Self {
+            message: payload_message(panic_info.payload()),
+            location: panic_info.location().map(Location::from),
+            thread_name: thread.name().map(str::to_owned),
+            thread_id: thread.id(),
+            timestamp: SystemTime::now(),
+            backtrace: match backtrace.status() {
+                BacktraceStatus::Captured => Some(Arc::new(backtrace)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Builds a report from a caught panic's payload alone, for
+    /// when the panic hook did not get to record it.
+    pub(crate) fn from_payload(payload: &(dyn Any + Send)) -> Self {
+        let thread = std::thread::current();
+
+        Self {
+            message: payload_message(payload),
+            location: None,
+            thread_name: thread.name().map(str::to_owned),
+            thread_id: thread.id(),
+            timestamp: SystemTime::now(),
+            backtrace: None,
+        }
+    }
+
+    /// The panic's message, or `Box<dyn Any>` if the payload
+    /// was not a string.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where the panic originated, if known.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// The name of the thread which panicked, if it had one.
+    pub fn thread_name(&self) -> Option<&str> {
+        self.thread_name.as_deref()
+    }
+
+    /// The id of the thread which panicked.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// When the panic was hushed.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// The backtrace of the panic, if backtraces were enabled
+    /// (see [`std::backtrace::Backtrace::capture`]).
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+}
+
+/// Formats the report the way the standard library's panic
+/// hook prints a panic.
+impl fmt::Display for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_header(f, ":\n")?;
+
+        if let Some(backtrace) = &self.backtrace {
+            write!(f, "\nstack backtrace:\n{}", backtrace)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PanicReport {
+    fn fmt_header(&self, f: &mut fmt::Formatter<'_>, separator: &str) -> fmt::Result {
+        write!(f, "thread '{}' panicked", self.thread_name().unwrap_or("<unnamed>"))?;
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        write!(f, "{}{}", separator, self.message)
+    }
+
+    /// Formats the report as much as `level` prints of it.
+    pub(crate) fn at_level(&self, level: HushLevel) -> AtLevel<'_> {
+        AtLevel { report: self, level }
+    }
+}
+
+pub(crate) struct AtLevel<'a> {
+    report: &'a PanicReport,
+    level: HushLevel,
+}
+
+impl fmt::Display for AtLevel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            HushLevel::Silent => Ok(()),
+            HushLevel::OneLine => self.report.fmt_header(f, ": "),
+            HushLevel::NoBacktrace => self.report.fmt_header(f, ":\n"),
+            HushLevel::Full => self.report.fmt(f),
+        }
+    }
+}
+
+/// Extracts the message of a panic payload the same way the
+/// standard library's hook does.
+pub(crate) fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_owned()
+    }
+}
@@ -0,0 +1,162 @@
+//!
+//! Mirrors of [`std::thread`]'s spawning functions whose
+//! threads inherit the hushes of the thread spawning them.
+//!
+//! ```
+//! let guard = hushed_panic::hush_this_test();
+//!
+//! let result = hushed_panic::thread::spawn(|| panic!("quiet")).join();
+//! assert!(result.is_err());
+//!
+//! // The child's panic was hushed and reported back.
+//! assert_eq!(guard.reports().len(), 1);
+//! ```
+//!
+
+use crate::frame::{Frame, FrameId};
+use std::io;
+use std::thread::{JoinHandle, ScopedJoinHandle};
+
+/// Spawns a new thread which inherits this thread's hushes,
+/// like [`std::thread::spawn`].
+///
+/// # Panics
+///
+/// Panics if the OS fails to create a thread, use
+/// [`Builder::spawn`] to recover from such errors.
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Builder::new().spawn(f).expect("failed to spawn thread")
+}
+
+/// A thread factory which mirrors [`std::thread::Builder`],
+/// whose threads inherit the hushes of the thread spawning them.
+#[derive(Debug)]
+pub struct Builder {
+    inner: std::thread::Builder,
+}
+
+impl Builder {
+    /// Creates a builder with the same defaults as
+    /// [`std::thread::Builder::new`].
+    pub fn new() -> Self {
+        Self {
+            inner: std::thread::Builder::new(),
+        }
+    }
+
+    /// Names the thread-to-be.
+    pub fn name(self, name: String) -> Self {
+        Self {
+            inner: self.inner.name(name),
+        }
+    }
+
+    /// Sets the size of the stack (in bytes) for the new thread.
+    pub fn stack_size(self, size: usize) -> Self {
+        Self {
+            inner: self.inner.stack_size(size),
+        }
+    }
+
+    /// Spawns a new thread which inherits this thread's hushes,
+    /// like [`std::thread::Builder::spawn`].
+    pub fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let inherited = crate::inherit_frames();
+
+        self.inner.spawn(move || {
+            let _inherited = Inherited::enter(inherited);
+            f()
+        })
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a scope for spawning scoped threads which inherit
+/// this thread's hushes, like [`std::thread::scope`].
+///
+/// ```
+/// let guard = hushed_panic::hush_this_test();
+/// let mut values = [1, 2, 3];
+///
+/// hushed_panic::thread::scope(|s| {
+///     for value in &mut values {
+///         let handle = s.spawn(move || {
+///             *value *= 2;
+///             assert!(*value < 6, "too big");
+///         });
+///         let _ = handle.join();
+///     }
+/// });
+///
+/// assert_eq!(values, [2, 4, 6]);
+/// assert_eq!(guard.reports().len(), 1);
+/// ```
+pub fn scope<'env, F, T>(f: F) -> T
+where
+    F: for<'scope> FnOnce(&Scope<'scope, 'env>) -> T,
+{
+    std::thread::scope(|inner| f(&Scope { inner }))
+}
+
+/// A scope to spawn scoped threads in, which mirrors
+/// [`std::thread::Scope`].
+///
+/// Create one by calling [`scope`].
+#[derive(Debug)]
+pub struct Scope<'scope, 'env: 'scope> {
+    inner: &'scope std::thread::Scope<'scope, 'env>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Spawns a new scoped thread which inherits this thread's
+    /// hushes, like [`std::thread::Scope::spawn`].
+    pub fn spawn<F, T>(&self, f: F) -> ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let inherited = crate::inherit_frames();
+
+        self.inner.spawn(move || {
+            let _inherited = Inherited::enter(inherited);
+            f()
+        })
+    }
+}
+
+/// Hushes inherited from a parent thread, which are undone when
+/// dropped at the end of the child thread.
+pub(crate) struct Inherited {
+    ids: Vec<FrameId>,
+}
+
+impl Inherited {
+    pub(crate) fn enter(frames: Vec<Frame>) -> Self {
+        crate::register_thread();
+
+        Self {
+            ids: frames.into_iter().map(crate::push_frame).collect(),
+        }
+    }
+}
+
+impl Drop for Inherited {
+    fn drop(&mut self) {
+        for &id in self.ids.iter().rev() {
+            crate::remove_frame(id);
+        }
+    }
+}
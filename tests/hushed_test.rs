@@ -0,0 +1,16 @@
+#[hushed_panic::hushed_test]
+fn hushes_caught_panics() {
+    let result = std::panic::catch_unwind(|| panic!("hushed"));
+    assert!(result.is_err());
+}
+
+#[hushed_panic::hushed_test]
+#[should_panic]
+fn hushes_the_failing_panic() {
+    panic!("hushed");
+}
+
+#[hushed_panic::hushed_test(expected = "index out of bounds")]
+fn expects_the_message() {
+    let _ = [1, 2, 3][std::hint::black_box(4)];
+}
@@ -0,0 +1,19 @@
+#[hushed_panic::should_panic_quietly]
+fn passes_on_any_hushed_panic() {
+    panic!("hushed");
+}
+
+#[hushed_panic::should_panic_quietly(expected = "index out of bounds")]
+fn passes_on_the_expected_message() {
+    let _ = [1, 2, 3][std::hint::black_box(4)];
+}
+
+#[hushed_panic::should_panic_quietly]
+#[should_panic(expected = "test did not panic as expected")]
+fn fails_without_a_panic() {}
+
+#[hushed_panic::should_panic_quietly(expected = "index out of bounds")]
+#[should_panic(expected = "test panicked with an unexpected message")]
+fn fails_on_another_message() {
+    panic!("something else");
+}